@@ -6,10 +6,31 @@ use std::str;
 /// An event reported by the terminal.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Event {
-    /// A key press.
-    Key(Key),
+    /// A key press, together with any Shift/Alt/Ctrl/Super modifiers held.
+    Key(Key, KeyModifiers),
+    /// A key was released.
+    ///
+    /// Only reported by terminals that implement the Kitty keyboard protocol
+    /// (`ESC [ > 1 u`), which is the only encoding able to tell presses and
+    /// releases apart.
+    KeyRelease(Key, KeyModifiers),
+    /// A key press auto-repeating while held down.
+    ///
+    /// Only reported by terminals that implement the Kitty keyboard protocol
+    /// (`ESC [ > 1 u`).
+    KeyRepeat(Key, KeyModifiers),
     /// A mouse button press, release or wheel use at specific coordinates.
     Mouse(MouseEvent),
+    /// Text pasted in bracketed paste mode (`ESC [ ? 2004 h`).
+    ///
+    /// The terminal wraps the pasted text between `ESC [ 200 ~` and
+    /// `ESC [ 201 ~`, letting us hand it back as a single chunk rather than
+    /// as a sequence of keystrokes.
+    Paste(String),
+    /// The terminal window gained focus (`ESC [ ? 1004 h` must be enabled).
+    FocusGained,
+    /// The terminal window lost focus (`ESC [ ? 1004 h` must be enabled).
+    FocusLost,
     /// An event that cannot currently be evaluated.
     Unsupported(Vec<u8>),
 }
@@ -18,17 +39,31 @@ pub enum Event {
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum MouseEvent {
     /// A mouse button was pressed.
-    ///
-    /// The coordinates are one-based.
-    Press(MouseButton, u16, u16),
+    Press(MouseButton, MouseCoordinate),
     /// A mouse button was released.
+    Release(MouseCoordinate),
+    /// The mouse moved with no button identified.
     ///
-    /// The coordinates are one-based.
-    Release(u16, u16),
-    /// A mouse button is held over the given coordinates.
-    ///
-    /// The coordinates are one-based.
-    Hold(u16, u16),
+    /// SGR reports this for motion with no button held, and for the press or
+    /// drag of a button that [`MouseButton`] has no variant for; the rxvt
+    /// encoding reports `Cb` = 64 this way.
+    Hold(MouseCoordinate),
+    /// The mouse moved while `MouseButton` was held (a drag).
+    Drag(MouseButton, MouseCoordinate),
+}
+
+/// Where a [`MouseEvent`] happened.
+///
+/// SGR mouse reporting is normally in one-based terminal cells, but a
+/// terminal with SGR-Pixels mode enabled (`ESC [ ? 1016 h`) reports raw pixel
+/// offsets instead. The two can't be told apart from the bytes alone, so
+/// which one a parser produces is controlled by its `sgr_pixels` flag.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MouseCoordinate {
+    /// One-based terminal cell coordinates.
+    Cell(u16, u16),
+    /// Raw pixel offsets (SGR-Pixels mode only).
+    Pixel(u16, u16),
 }
 
 /// A mouse button.
@@ -48,6 +83,14 @@ pub enum MouseButton {
     ///
     /// This event is typically only used with Mouse::Press.
     WheelDown,
+    /// Mouse wheel is going left.
+    ///
+    /// This event is typically only used with Mouse::Press.
+    WheelLeft,
+    /// Mouse wheel is going right.
+    ///
+    /// This event is typically only used with Mouse::Press.
+    WheelRight,
 }
 
 /// A key.
@@ -96,7 +139,71 @@ pub enum Key {
     __IsNotComplete,
 }
 
+/// Shift/Alt/Ctrl/Super modifier flags, as carried alongside a [`Key`] by
+/// [`Event::Key`], [`Event::KeyRelease`] and [`Event::KeyRepeat`].
+///
+/// These mirror the `mods` field of the extended CSI encodings: `ESC [ num ;
+/// mods ~` for `~`-terminated sequences, `ESC [ 1 ; mods letter` for
+/// letter-terminated ones, and the second parameter of a Kitty keyboard
+/// protocol `ESC [ ... ; mods u` sequence.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct KeyModifiers(u8);
+
+impl KeyModifiers {
+    /// The Shift key.
+    pub const SHIFT: KeyModifiers = KeyModifiers(0b0001);
+    /// The Alt (Meta) key.
+    pub const ALT: KeyModifiers = KeyModifiers(0b0010);
+    /// The Ctrl key.
+    pub const CTRL: KeyModifiers = KeyModifiers(0b0100);
+    /// The Super (Windows/Command) key.
+    ///
+    /// Bit 3 of the decoded `mods` parameter. The Kitty keyboard protocol
+    /// uses it for Super; the `~`- and letter-terminated CSI encodings decode
+    /// the same bit into this flag as well.
+    pub const SUPER: KeyModifiers = KeyModifiers(0b1000);
+
+    /// No modifiers.
+    pub fn empty() -> KeyModifiers {
+        KeyModifiers(0)
+    }
+
+    /// Whether no modifier bits are set.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether `self` has every bit set in `other`.
+    pub fn contains(&self, other: KeyModifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Decode a wire `mods` parameter (1-based, as sent on the wire) into a
+    /// set of flags.
+    fn from_param(n: u16) -> KeyModifiers {
+        KeyModifiers(n.saturating_sub(1) as u8 & 0b1111)
+    }
+}
+
+impl ::std::ops::BitOr for KeyModifiers {
+    type Output = KeyModifiers;
+
+    fn bitor(self, other: KeyModifiers) -> KeyModifiers {
+        KeyModifiers(self.0 | other.0)
+    }
+}
+
 pub fn parse_event<I>(item: u8, iter: &mut I) -> Result<(Event, Vec<u8>), Error>
+where
+    I: Iterator<Item = Result<u8, Error>>,
+{
+    parse_event_ext(item, iter, false)
+}
+
+/// Like [`parse_event`], but `sgr_pixels` tells the parser whether the
+/// terminal has SGR-Pixels mode enabled (`ESC [ ? 1016 h`), in which case SGR
+/// mouse coordinates are raw pixel offsets rather than one-based cells.
+pub fn parse_event_ext<I>(item: u8, iter: &mut I, sgr_pixels: bool) -> Result<(Event, Vec<u8>), Error>
 where
     I: Iterator<Item = Result<u8, Error>>,
 {
@@ -107,7 +214,7 @@ where
                 buf.push(byte);
             }
         });
-        try_parse_event(item, &mut iter)
+        try_parse_event(item, &mut iter, sgr_pixels)
     };
     result
         .or_else(|err| {
@@ -121,7 +228,7 @@ where
 }
 
 /// Parse an Event from `item` and possibly subsequent bytes through `iter`.
-fn try_parse_event<I>(item: u8, iter: &mut I) -> Result<Event, Error>
+fn try_parse_event<I>(item: u8, iter: &mut I, sgr_pixels: bool) -> Result<Event, Error>
 where
     I: Iterator<Item = Result<u8, Error>>,
 {
@@ -132,7 +239,7 @@ where
                 Some(Ok(b'O')) => {
                     match iter.next() {
                         // F1-F4
-                        Some(Ok(val @ b'P'...b'S')) => Event::Key(Key::F(1 + val - b'P')),
+                        Some(Ok(val @ b'P'...b'S')) => Event::Key(Key::F(1 + val - b'P'), KeyModifiers::empty()),
                         Some(Ok(val)) => Event::Unsupported(vec![b'\x1B', b'0', val]),
                         Some(Err(e)) => return Err(e),
                         None => Event::Unsupported(vec![b'\x1B', b'0']),
@@ -140,25 +247,25 @@ where
                 }
                 Some(Ok(b'[')) => {
                     // This is a CSI sequence.
-                    parse_csi(iter)?
+                    parse_csi(iter, sgr_pixels)?
                 }
                 Some(Ok(c)) => {
                     let ch = parse_utf8_char(c, iter);
-                    Event::Key(Key::Alt(try!(ch)))
+                    Event::Key(Key::Alt(try!(ch)), KeyModifiers::empty())
                 }
                 Some(Err(e)) => return Err(e),
                 None => Event::Unsupported(vec![b'\x1B']),
             })
         }
-        b'\n' | b'\r' => Ok(Event::Key(Key::Char('\n'))),
-        b'\t' => Ok(Event::Key(Key::Char('\t'))),
-        b'\x7F' => Ok(Event::Key(Key::Backspace)),
-        c @ b'\x01'...b'\x1A' => Ok(Event::Key(Key::Ctrl((c as u8 - 0x1 + b'a') as char))),
-        c @ b'\x1C'...b'\x1F' => Ok(Event::Key(Key::Ctrl((c as u8 - 0x1C + b'4') as char))),
-        b'\0' => Ok(Event::Key(Key::Null)),
+        b'\n' | b'\r' => Ok(Event::Key(Key::Char('\n'), KeyModifiers::empty())),
+        b'\t' => Ok(Event::Key(Key::Char('\t'), KeyModifiers::empty())),
+        b'\x7F' => Ok(Event::Key(Key::Backspace, KeyModifiers::empty())),
+        c @ b'\x01'...b'\x1A' => Ok(Event::Key(Key::Ctrl((c as u8 - 0x1 + b'a') as char), KeyModifiers::empty())),
+        c @ b'\x1C'...b'\x1F' => Ok(Event::Key(Key::Ctrl((c as u8 - 0x1C + b'4') as char), KeyModifiers::empty())),
+        b'\0' => Ok(Event::Key(Key::Null, KeyModifiers::empty())),
         c => Ok({
             let ch = parse_utf8_char(c, iter);
-            Event::Key(Key::Char(try!(ch)))
+            Event::Key(Key::Char(try!(ch)), KeyModifiers::empty())
         }),
     }
 }
@@ -180,23 +287,25 @@ fn err_unexpected_eof() -> Error {
 /// Parses a CSI sequence, just after reading ^[
 ///
 /// Returns Ok(Event::Unsupported) if an unrecognized sequence is found.
-fn parse_csi<I>(iter: &mut I) -> Result<Event, Error>
+fn parse_csi<I>(iter: &mut I, sgr_pixels: bool) -> Result<Event, Error>
 where
     I: Iterator<Item = Result<u8, Error>>,
 {
     Ok(match pop(iter)? {
         b'[' => match iter.next() {
             None => return Err(err_unexpected_eof()),
-            Some(Ok(val @ b'A'...b'E')) => Event::Key(Key::F(1 + val - b'A')),
+            Some(Ok(val @ b'A'...b'E')) => Event::Key(Key::F(1 + val - b'A'), KeyModifiers::empty()),
             Some(Ok(_)) => return Err(err_invalid_input()),
             Some(Err(e)) => return Err(e),
         },
-        b'D' => Event::Key(Key::Left),
-        b'C' => Event::Key(Key::Right),
-        b'A' => Event::Key(Key::Up),
-        b'B' => Event::Key(Key::Down),
-        b'H' => Event::Key(Key::Home),
-        b'F' => Event::Key(Key::End),
+        b'D' => Event::Key(Key::Left, KeyModifiers::empty()),
+        b'C' => Event::Key(Key::Right, KeyModifiers::empty()),
+        b'A' => Event::Key(Key::Up, KeyModifiers::empty()),
+        b'B' => Event::Key(Key::Down, KeyModifiers::empty()),
+        b'H' => Event::Key(Key::Home, KeyModifiers::empty()),
+        b'F' => Event::Key(Key::End, KeyModifiers::empty()),
+        b'I' => Event::FocusGained,
+        b'O' => Event::FocusLost,
         b'M' => {
             // X10 emulation mouse encoding: ESC [ CB Cx Cy (6 characters only).
 
@@ -208,23 +317,24 @@ where
             // (1, 1) are the coords for upper left.
             let cx = b2.saturating_sub(32) as u16;
             let cy = b3.saturating_sub(32) as u16;
+            let coord = MouseCoordinate::Cell(cx, cy);
             Event::Mouse(match cb & 0b11 {
                 0 => {
                     if cb & 0x40 != 0 {
-                        MouseEvent::Press(MouseButton::WheelUp, cx, cy)
+                        MouseEvent::Press(MouseButton::WheelUp, coord)
                     } else {
-                        MouseEvent::Press(MouseButton::Left, cx, cy)
+                        MouseEvent::Press(MouseButton::Left, coord)
                     }
                 }
                 1 => {
                     if cb & 0x40 != 0 {
-                        MouseEvent::Press(MouseButton::WheelDown, cx, cy)
+                        MouseEvent::Press(MouseButton::WheelDown, coord)
                     } else {
-                        MouseEvent::Press(MouseButton::Middle, cx, cy)
+                        MouseEvent::Press(MouseButton::Middle, coord)
                     }
                 }
-                2 => MouseEvent::Press(MouseButton::Right, cx, cy),
-                3 => MouseEvent::Release(cx, cy),
+                2 => MouseEvent::Press(MouseButton::Right, coord),
+                3 => MouseEvent::Release(coord),
                 _ => return Err(err_invalid_input()),
             })
         }
@@ -249,28 +359,7 @@ where
             let cx = pop(nums)?;
             let cy = pop(nums)?;
 
-            let event = match cb {
-                0...2 | 64...65 => {
-                    let button = match cb {
-                        0 => MouseButton::Left,
-                        1 => MouseButton::Middle,
-                        2 => MouseButton::Right,
-                        64 => MouseButton::WheelUp,
-                        65 => MouseButton::WheelDown,
-                        _ => unreachable!(),
-                    };
-                    match c {
-                        b'M' => MouseEvent::Press(button, cx, cy),
-                        b'm' => MouseEvent::Release(cx, cy),
-                        _ => return Err(err_invalid_input()),
-                    }
-                }
-                32 => MouseEvent::Hold(cx, cy),
-                3 => MouseEvent::Release(cx, cy),
-                _ => return Err(err_invalid_input()),
-            };
-
-            Event::Mouse(event)
+            Event::Mouse(decode_sgr_mouse(cb, cx, cy, c, sgr_pixels)?)
         }
         mut c @ b'0'...b'9' => {
             // Numbered escape code.
@@ -300,14 +389,15 @@ where
                     let cb = pop(&mut nums)?;
                     let cx = pop(&mut nums)?;
                     let cy = pop(&mut nums)?;
+                    let coord = MouseCoordinate::Cell(cx, cy);
 
                     let event = match cb {
-                        32 => MouseEvent::Press(MouseButton::Left, cx, cy),
-                        33 => MouseEvent::Press(MouseButton::Middle, cx, cy),
-                        34 => MouseEvent::Press(MouseButton::Right, cx, cy),
-                        35 => MouseEvent::Release(cx, cy),
-                        64 => MouseEvent::Hold(cx, cy),
-                        96 | 97 => MouseEvent::Press(MouseButton::WheelUp, cx, cy),
+                        32 => MouseEvent::Press(MouseButton::Left, coord),
+                        33 => MouseEvent::Press(MouseButton::Middle, coord),
+                        34 => MouseEvent::Press(MouseButton::Right, coord),
+                        35 => MouseEvent::Release(coord),
+                        64 => MouseEvent::Hold(coord),
+                        96 | 97 => MouseEvent::Press(MouseButton::WheelUp, coord),
                         _ => {
                             return Err(err_invalid_input());
                         }
@@ -320,31 +410,75 @@ where
                     let str_buf = String::from_utf8(buf).map_err(|_| err_invalid_input())?;
 
                     // This CSI sequence can be a list of semicolon-separated
-                    // numbers.
+                    // numbers: `num` or `num ; mods`.
                     let mut nums = str_buf
                         .split(';')
                         .map(|n| n.parse().map_err(|_| err_invalid_input()));
 
                     let num = pop(&mut nums)?;
 
-                    // TODO: handle multiple values for key modififiers (ex: values
-                    // [3, 2] means Shift+Delete)
-                    if let Some(_) = nums.next() {
+                    // Bracketed paste start marker: `ESC [ 200 ~ ... ESC [
+                    // 201 ~`. Everything up to the end marker is the pasted
+                    // text, not individual keystrokes.
+                    if num == 200 {
+                        return parse_bracketed_paste(iter);
+                    }
+
+                    let mods = match nums.next() {
+                        Some(mods) => Some(KeyModifiers::from_param(mods?)),
+                        None => None,
+                    };
+                    if nums.next().is_some() {
                         return Err(err_invalid_input());
                     }
 
-                    match num {
-                        1 | 7 => Event::Key(Key::Home),
-                        2 => Event::Key(Key::Insert),
-                        3 => Event::Key(Key::Delete),
-                        4 | 8 => Event::Key(Key::End),
-                        5 => Event::Key(Key::PageUp),
-                        6 => Event::Key(Key::PageDown),
-                        v @ 11...15 => Event::Key(Key::F(v - 10)),
-                        v @ 17...21 => Event::Key(Key::F(v - 11)),
-                        v @ 23...24 => Event::Key(Key::F(v - 12)),
+                    let key = match num {
+                        1 | 7 => Key::Home,
+                        2 => Key::Insert,
+                        3 => Key::Delete,
+                        4 | 8 => Key::End,
+                        5 => Key::PageUp,
+                        6 => Key::PageDown,
+                        v @ 11...15 => Key::F((v - 10) as u8),
+                        v @ 17...21 => Key::F((v - 11) as u8),
+                        v @ 23...24 => Key::F((v - 12) as u8),
                         _ => return Err(err_invalid_input()),
+                    };
+
+                    Event::Key(key, mods.unwrap_or_else(KeyModifiers::empty))
+                }
+                // Modified arrow/navigation key, e.g. `ESC [ 1 ; 5 C` for
+                // Ctrl+Right. The `1` is fixed and ignored; only the `mods`
+                // parameter matters.
+                val @ b'A' | val @ b'B' | val @ b'C' | val @ b'D' | val @ b'H' | val @ b'F' => {
+                    let str_buf = String::from_utf8(buf).map_err(|_| err_invalid_input())?;
+                    let mut nums = str_buf
+                        .split(';')
+                        .map(|n| n.parse().map_err(|_| err_invalid_input()));
+
+                    let _ = pop(&mut nums)?;
+                    let mods = KeyModifiers::from_param(pop(&mut nums)?);
+                    if nums.next().is_some() {
+                        return Err(err_invalid_input());
                     }
+
+                    let key = match val {
+                        b'A' => Key::Up,
+                        b'B' => Key::Down,
+                        b'C' => Key::Right,
+                        b'D' => Key::Left,
+                        b'H' => Key::Home,
+                        b'F' => Key::End,
+                        _ => unreachable!(),
+                    };
+
+                    Event::Key(key, mods)
+                }
+                // Kitty keyboard protocol (`ESC [ > 1 u` progressive
+                // enhancement): `ESC [ codepoint ; mods (: event-type) u`.
+                b'u' => {
+                    let str_buf = String::from_utf8(buf).map_err(|_| err_invalid_input())?;
+                    parse_kitty_key(&str_buf)?
                 }
                 _ => return Err(err_invalid_input()),
             }
@@ -353,6 +487,541 @@ where
     })
 }
 
+/// Decode the `Cb` field of an SGR mouse report (`ESC [ < Cb ; Cx ; Cy M/m`)
+/// into a [`MouseEvent`].
+///
+/// Bits 0-1 plus bit 6 (64) and bit 7 (128) select the button, bit 5 (32)
+/// marks motion (so a button held while moving is reported as a drag, and
+/// motion with no button as a plain [`MouseEvent::Hold`]). `final_byte` is
+/// `M` for press/drag/motion or `m` for release. `cx`/`cy` are reported as
+/// raw pixel offsets instead of one-based cells when `sgr_pixels` is set,
+/// mirroring whether the terminal has SGR-Pixels mode (`ESC [ ? 1016 h`)
+/// enabled -- the wire format can't tell the two apart.
+fn decode_sgr_mouse(
+    cb: u16,
+    cx: u16,
+    cy: u16,
+    final_byte: u8,
+    sgr_pixels: bool,
+) -> Result<MouseEvent, Error> {
+    let coord = if sgr_pixels {
+        MouseCoordinate::Pixel(cx, cy)
+    } else {
+        MouseCoordinate::Cell(cx, cy)
+    };
+
+    let motion = cb & 0b0010_0000 != 0;
+    // The button bits ignore the motion bit. `MouseButton` doesn't have a
+    // variant for xterm's "extra" buttons (bit 7 / 128+, e.g. side/forward
+    // buttons on a gaming mouse), so those report as an unclassified
+    // hold/release (like a bare motion event) rather than vanishing as
+    // `Unsupported`.
+    let button = match cb & !0b0010_0000 {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        3 => None,
+        64 => Some(MouseButton::WheelUp),
+        65 => Some(MouseButton::WheelDown),
+        66 => Some(MouseButton::WheelLeft),
+        67 => Some(MouseButton::WheelRight),
+        n if n >= 128 => None,
+        _ => return Err(err_invalid_input()),
+    };
+
+    Ok(match (motion, button) {
+        (true, Some(button)) => MouseEvent::Drag(button, coord),
+        (true, None) => MouseEvent::Hold(coord),
+        // No button identified (either cb = 3, or an "extra" button bit 7
+        // doesn't model): still distinguish press from release via
+        // `final_byte`, same as the classified-button case below.
+        (false, None) => match final_byte {
+            b'M' => MouseEvent::Hold(coord),
+            b'm' => MouseEvent::Release(coord),
+            _ => return Err(err_invalid_input()),
+        },
+        (false, Some(button)) => match final_byte {
+            b'M' => MouseEvent::Press(button, coord),
+            b'm' => MouseEvent::Release(coord),
+            _ => return Err(err_invalid_input()),
+        },
+    })
+}
+
+/// Decode a Kitty keyboard protocol report (`ESC [ codepoint ; mods :
+/// event-type u`), just after the leading `ESC [` has been consumed and the
+/// parameters collected into `str_buf`.
+///
+/// `mods` uses the same bit layout as the CSI-modifier encoding; the
+/// `:`-suffixed event type defaults to `1` (press) when absent.
+fn parse_kitty_key(str_buf: &str) -> Result<Event, Error> {
+    let mut params = str_buf.splitn(2, ';');
+    let codepoint: u32 = params
+        .next()
+        .unwrap()
+        .parse()
+        .map_err(|_| err_invalid_input())?;
+
+    let (mods, event_kind) = match params.next() {
+        Some(rest) => {
+            let mut sub = rest.splitn(2, ':');
+            let mods = sub
+                .next()
+                .unwrap()
+                .parse()
+                .map_err(|_| err_invalid_input())?;
+            let event_kind = match sub.next() {
+                Some(kind) => kind.parse().map_err(|_| err_invalid_input())?,
+                None => 1,
+            };
+            (KeyModifiers::from_param(mods), event_kind)
+        }
+        None => (KeyModifiers::empty(), 1),
+    };
+
+    let key = kitty_functional_key(codepoint)?;
+
+    Ok(match event_kind {
+        1 => Event::Key(key, mods),
+        2 => Event::KeyRepeat(key, mods),
+        3 => Event::KeyRelease(key, mods),
+        _ => return Err(err_invalid_input()),
+    })
+}
+
+/// Map a Kitty keyboard protocol codepoint to a [`Key`]. Codepoints below
+/// `57344` are plain Unicode scalar values; functional keys are assigned
+/// codepoints in the Unicode private-use area starting at `57344`.
+fn kitty_functional_key(codepoint: u32) -> Result<Key, Error> {
+    Ok(match codepoint {
+        57344 => Key::Esc,
+        57345 => Key::Char('\n'),
+        57346 => Key::Char('\t'),
+        57347 => Key::Backspace,
+        57348 => Key::Insert,
+        57349 => Key::Delete,
+        57350 => Key::Left,
+        57351 => Key::Right,
+        57352 => Key::Up,
+        57353 => Key::Down,
+        57354 => Key::PageUp,
+        57355 => Key::PageDown,
+        57356 => Key::Home,
+        57357 => Key::End,
+        v @ 57364...57375 => Key::F((v - 57364 + 1) as u8),
+        // The rest of the Kitty functional-key range (CapsLock, media keys,
+        // bare modifier keys, F13+, etc.) isn't mapped to a `Key` variant
+        // yet -- report it as invalid rather than silently misreading it as
+        // a bogus printable character.
+        57344...63743 => return Err(err_invalid_input()),
+        _ => {
+            let ch = ::std::char::from_u32(codepoint).ok_or_else(err_invalid_input)?;
+            Key::Char(ch)
+        }
+    })
+}
+
+/// Reads the body of a bracketed paste, just after `ESC [ 200 ~` has been
+/// consumed, through to the `ESC [ 201 ~` end marker.
+fn parse_bracketed_paste<I>(iter: &mut I) -> Result<Event, Error>
+where
+    I: Iterator<Item = Result<u8, Error>>,
+{
+    const END_MARKER: &[u8] = b"\x1B[201~";
+
+    let mut buf = Vec::new();
+    loop {
+        match iter.next() {
+            Some(Ok(b)) => {
+                buf.push(b);
+                if buf.ends_with(END_MARKER) {
+                    buf.truncate(buf.len() - END_MARKER.len());
+                    return String::from_utf8(buf)
+                        .map(Event::Paste)
+                        .map_err(|_| err_invalid_input());
+                }
+            }
+            Some(Err(e)) => return Err(e),
+            None => {
+                let mut unsupported = b"\x1B[200~".to_vec();
+                unsupported.extend(buf);
+                return Ok(Event::Unsupported(unsupported));
+            }
+        }
+    }
+}
+
+/// A non-blocking counterpart to [`parse_event`] that reads from a `buffer`
+/// slice instead of draining a blocking iterator.
+///
+/// `buffer` may hold an incomplete prefix of an event, as happens when it is
+/// filled from a non-blocking or async read. `input_available` tells the
+/// parser whether more bytes may still arrive:
+///
+/// - If a full event is recognized, returns `Ok(Some((event, consumed)))`,
+///   where `consumed` is how many bytes of `buffer` it used.
+/// - If `buffer` holds a valid but incomplete prefix and `input_available`
+///   is `true`, returns `Ok(None)` so the caller can retry once more bytes
+///   have been read.
+/// - If the prefix is definitively invalid, returns `Err`. So does an
+///   incomplete prefix when `input_available` is `false` (no more bytes are
+///   coming), except that a lone `Esc` is reported as a key (see below), and
+///   a truncated `ESC O` or an unterminated bracketed paste as
+///   [`Event::Unsupported`].
+///
+/// `input_available` resolves the classic ambiguity around a lone `Esc`
+/// keypress: `&[0x1B]` yields `Ok(None)` while more input may be pending,
+/// but `Ok(Some((Event::Key(Key::Esc, KeyModifiers::empty()), 1)))` once the
+/// caller knows no more input is coming.
+pub fn parse_event_buffered(
+    buffer: &[u8],
+    input_available: bool,
+) -> Result<Option<(Event, usize)>, Error> {
+    parse_event_buffered_ext(buffer, input_available, false)
+}
+
+/// Like [`parse_event_buffered`], but `sgr_pixels` tells the parser whether
+/// the terminal has SGR-Pixels mode enabled (`ESC [ ? 1016 h`), in which case
+/// SGR mouse coordinates are raw pixel offsets rather than one-based cells.
+pub fn parse_event_buffered_ext(
+    buffer: &[u8],
+    input_available: bool,
+    sgr_pixels: bool,
+) -> Result<Option<(Event, usize)>, Error> {
+    if buffer.is_empty() {
+        return Ok(None);
+    }
+
+    let mut pos = 0;
+    match try_parse_event_buffered(buffer, &mut pos, input_available, sgr_pixels) {
+        Ok(event) => Ok(Some((event, pos))),
+        Err(ref e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Signals that `buffer` holds a valid but incomplete prefix: more bytes may
+/// resolve it, so the caller should wait and retry.
+fn err_incomplete() -> Error {
+    Error::from(ErrorKind::WouldBlock)
+}
+
+/// Reads one byte from `buf` at `pos`, advancing it.
+///
+/// `Ok(Some(b))` is a byte; `Ok(None)` is a genuine end of input (no more
+/// bytes will ever come); `Err` with [`ErrorKind::WouldBlock`] means `buf`
+/// simply doesn't hold the next byte *yet*.
+fn next_buffered(buf: &[u8], pos: &mut usize, input_available: bool) -> Result<Option<u8>, Error> {
+    match buf.get(*pos) {
+        Some(&b) => {
+            *pos += 1;
+            Ok(Some(b))
+        }
+        None if input_available => Err(err_incomplete()),
+        None => Ok(None),
+    }
+}
+
+/// Like [`next_buffered`], but a genuine end of input is an error: used where
+/// the grammar requires another byte to continue.
+fn pop_buffered(buf: &[u8], pos: &mut usize, input_available: bool) -> Result<u8, Error> {
+    next_buffered(buf, pos, input_available)?.ok_or_else(err_unexpected_eof)
+}
+
+/// Buffered counterpart to [`try_parse_event`].
+fn try_parse_event_buffered(
+    buf: &[u8],
+    pos: &mut usize,
+    input_available: bool,
+    sgr_pixels: bool,
+) -> Result<Event, Error> {
+    match pop_buffered(buf, pos, input_available)? {
+        b'\x1B' => match next_buffered(buf, pos, input_available)? {
+            Some(b'O') => match next_buffered(buf, pos, input_available)? {
+                // F1-F4
+                Some(val @ b'P'...b'S') => Ok(Event::Key(Key::F(1 + val - b'P'), KeyModifiers::empty())),
+                Some(val) => Ok(Event::Unsupported(vec![b'\x1B', b'0', val])),
+                None => Ok(Event::Unsupported(vec![b'\x1B', b'0'])),
+            },
+            Some(b'[') => parse_csi_buffered(buf, pos, input_available, sgr_pixels),
+            Some(c) => {
+                let ch = parse_utf8_char_buffered(c, buf, pos, input_available)?;
+                Ok(Event::Key(Key::Alt(ch), KeyModifiers::empty()))
+            }
+            // No more input will ever come: unlike the blocking parser, a
+            // lone Esc is unambiguous here, so report it as a key.
+            None => Ok(Event::Key(Key::Esc, KeyModifiers::empty())),
+        },
+        b'\n' | b'\r' => Ok(Event::Key(Key::Char('\n'), KeyModifiers::empty())),
+        b'\t' => Ok(Event::Key(Key::Char('\t'), KeyModifiers::empty())),
+        b'\x7F' => Ok(Event::Key(Key::Backspace, KeyModifiers::empty())),
+        c @ b'\x01'...b'\x1A' => Ok(Event::Key(Key::Ctrl((c - 0x1 + b'a') as char), KeyModifiers::empty())),
+        c @ b'\x1C'...b'\x1F' => Ok(Event::Key(Key::Ctrl((c - 0x1C + b'4') as char), KeyModifiers::empty())),
+        b'\0' => Ok(Event::Key(Key::Null, KeyModifiers::empty())),
+        c => {
+            let ch = parse_utf8_char_buffered(c, buf, pos, input_available)?;
+            Ok(Event::Key(Key::Char(ch), KeyModifiers::empty()))
+        }
+    }
+}
+
+/// Buffered counterpart to [`parse_csi`].
+fn parse_csi_buffered(
+    buf: &[u8],
+    pos: &mut usize,
+    input_available: bool,
+    sgr_pixels: bool,
+) -> Result<Event, Error> {
+    Ok(match pop_buffered(buf, pos, input_available)? {
+        b'[' => match next_buffered(buf, pos, input_available)? {
+            None => return Err(err_unexpected_eof()),
+            Some(val @ b'A'...b'E') => Event::Key(Key::F(1 + val - b'A'), KeyModifiers::empty()),
+            Some(_) => return Err(err_invalid_input()),
+        },
+        b'D' => Event::Key(Key::Left, KeyModifiers::empty()),
+        b'C' => Event::Key(Key::Right, KeyModifiers::empty()),
+        b'A' => Event::Key(Key::Up, KeyModifiers::empty()),
+        b'B' => Event::Key(Key::Down, KeyModifiers::empty()),
+        b'H' => Event::Key(Key::Home, KeyModifiers::empty()),
+        b'F' => Event::Key(Key::End, KeyModifiers::empty()),
+        b'I' => Event::FocusGained,
+        b'O' => Event::FocusLost,
+        b'M' => {
+            // X10 emulation mouse encoding: ESC [ CB Cx Cy (6 characters only).
+            let b1 = pop_buffered(buf, pos, input_available)?;
+            let b2 = pop_buffered(buf, pos, input_available)?;
+            let b3 = pop_buffered(buf, pos, input_available)?;
+
+            let cb = b1 as i8 - 32;
+            let cx = b2.saturating_sub(32) as u16;
+            let cy = b3.saturating_sub(32) as u16;
+            let coord = MouseCoordinate::Cell(cx, cy);
+            Event::Mouse(match cb & 0b11 {
+                0 => {
+                    if cb & 0x40 != 0 {
+                        MouseEvent::Press(MouseButton::WheelUp, coord)
+                    } else {
+                        MouseEvent::Press(MouseButton::Left, coord)
+                    }
+                }
+                1 => {
+                    if cb & 0x40 != 0 {
+                        MouseEvent::Press(MouseButton::WheelDown, coord)
+                    } else {
+                        MouseEvent::Press(MouseButton::Middle, coord)
+                    }
+                }
+                2 => MouseEvent::Press(MouseButton::Right, coord),
+                3 => MouseEvent::Release(coord),
+                _ => return Err(err_invalid_input()),
+            })
+        }
+        b'<' => {
+            // xterm mouse encoding: ESC [ < Cb ; Cx ; Cy (;) (M or m)
+            let mut numbuf = Vec::new();
+            let mut c = pop_buffered(buf, pos, input_available)?;
+            while match c {
+                b'm' | b'M' => false,
+                _ => true,
+            } {
+                numbuf.push(c);
+                c = pop_buffered(buf, pos, input_available)?;
+            }
+            let str_buf = String::from_utf8(numbuf).map_err(|_| err_invalid_input())?;
+            let nums = &mut str_buf
+                .split(';')
+                .map(|n| n.parse::<u16>().map_err(|_| err_invalid_input()));
+
+            let cb = pop(nums)?;
+            let cx = pop(nums)?;
+            let cy = pop(nums)?;
+
+            Event::Mouse(decode_sgr_mouse(cb, cx, cy, c, sgr_pixels)?)
+        }
+        mut c @ b'0'...b'9' => {
+            // Numbered escape code.
+            let mut numbuf = Vec::new();
+            numbuf.push(c);
+            // The final byte of a CSI sequence can be in the range 64-126, so
+            // let's keep reading anything else.
+            loop {
+                match next_buffered(buf, pos, input_available)? {
+                    Some(n) => {
+                        c = n;
+                        if c < 64 || c > 126 {
+                            numbuf.push(c);
+                        } else {
+                            break;
+                        }
+                    }
+                    None => return Err(err_invalid_input()),
+                }
+            }
+
+            match c {
+                // rxvt mouse encoding: ESC [ Cb ; Cx ; Cy ; M
+                b'M' => {
+                    let str_buf = String::from_utf8(numbuf).map_err(|_| err_invalid_input())?;
+
+                    let mut nums = str_buf
+                        .split(';')
+                        .map(|n| n.parse().map_err(|_| err_invalid_input()));
+
+                    let cb = pop(&mut nums)?;
+                    let cx = pop(&mut nums)?;
+                    let cy = pop(&mut nums)?;
+                    let coord = MouseCoordinate::Cell(cx, cy);
+
+                    let event = match cb {
+                        32 => MouseEvent::Press(MouseButton::Left, coord),
+                        33 => MouseEvent::Press(MouseButton::Middle, coord),
+                        34 => MouseEvent::Press(MouseButton::Right, coord),
+                        35 => MouseEvent::Release(coord),
+                        64 => MouseEvent::Hold(coord),
+                        96 | 97 => MouseEvent::Press(MouseButton::WheelUp, coord),
+                        _ => {
+                            return Err(err_invalid_input());
+                        }
+                    };
+
+                    Event::Mouse(event)
+                }
+                // Special key code.
+                b'~' => {
+                    let str_buf = String::from_utf8(numbuf).map_err(|_| err_invalid_input())?;
+
+                    let mut nums = str_buf
+                        .split(';')
+                        .map(|n| n.parse().map_err(|_| err_invalid_input()));
+
+                    let num = pop(&mut nums)?;
+
+                    if num == 200 {
+                        return parse_bracketed_paste_buffered(buf, pos, input_available);
+                    }
+
+                    let mods = match nums.next() {
+                        Some(mods) => Some(KeyModifiers::from_param(mods?)),
+                        None => None,
+                    };
+                    if nums.next().is_some() {
+                        return Err(err_invalid_input());
+                    }
+
+                    let key = match num {
+                        1 | 7 => Key::Home,
+                        2 => Key::Insert,
+                        3 => Key::Delete,
+                        4 | 8 => Key::End,
+                        5 => Key::PageUp,
+                        6 => Key::PageDown,
+                        v @ 11...15 => Key::F((v - 10) as u8),
+                        v @ 17...21 => Key::F((v - 11) as u8),
+                        v @ 23...24 => Key::F((v - 12) as u8),
+                        _ => return Err(err_invalid_input()),
+                    };
+
+                    Event::Key(key, mods.unwrap_or_else(KeyModifiers::empty))
+                }
+                // Modified arrow/navigation key, e.g. `ESC [ 1 ; 5 C` for
+                // Ctrl+Right.
+                val @ b'A' | val @ b'B' | val @ b'C' | val @ b'D' | val @ b'H' | val @ b'F' => {
+                    let str_buf = String::from_utf8(numbuf).map_err(|_| err_invalid_input())?;
+                    let mut nums = str_buf
+                        .split(';')
+                        .map(|n| n.parse().map_err(|_| err_invalid_input()));
+
+                    let _ = pop(&mut nums)?;
+                    let mods = KeyModifiers::from_param(pop(&mut nums)?);
+                    if nums.next().is_some() {
+                        return Err(err_invalid_input());
+                    }
+
+                    let key = match val {
+                        b'A' => Key::Up,
+                        b'B' => Key::Down,
+                        b'C' => Key::Right,
+                        b'D' => Key::Left,
+                        b'H' => Key::Home,
+                        b'F' => Key::End,
+                        _ => unreachable!(),
+                    };
+
+                    Event::Key(key, mods)
+                }
+                // Kitty keyboard protocol (`ESC [ > 1 u` progressive
+                // enhancement): `ESC [ codepoint ; mods (: event-type) u`.
+                b'u' => {
+                    let str_buf = String::from_utf8(numbuf).map_err(|_| err_invalid_input())?;
+                    parse_kitty_key(&str_buf)?
+                }
+                _ => return Err(err_invalid_input()),
+            }
+        }
+        _ => return Err(err_invalid_input()),
+    })
+}
+
+/// Buffered counterpart to [`parse_bracketed_paste`].
+fn parse_bracketed_paste_buffered(
+    buf: &[u8],
+    pos: &mut usize,
+    input_available: bool,
+) -> Result<Event, Error> {
+    const END_MARKER: &[u8] = b"\x1B[201~";
+
+    let start = *pos;
+    match buf[start..]
+        .windows(END_MARKER.len())
+        .position(|w| w == END_MARKER)
+    {
+        Some(idx) => {
+            let end = start + idx;
+            *pos = end + END_MARKER.len();
+            String::from_utf8(buf[start..end].to_vec())
+                .map(Event::Paste)
+                .map_err(|_| err_invalid_input())
+        }
+        None if input_available => Err(err_incomplete()),
+        None => {
+            *pos = buf.len();
+            let mut unsupported = b"\x1B[200~".to_vec();
+            unsupported.extend_from_slice(&buf[start..]);
+            Ok(Event::Unsupported(unsupported))
+        }
+    }
+}
+
+/// Buffered counterpart to [`parse_utf8_char`].
+fn parse_utf8_char_buffered(
+    c: u8,
+    buf: &[u8],
+    pos: &mut usize,
+    input_available: bool,
+) -> Result<char, Error> {
+    let error = || Error::new(ErrorKind::Other, "Input character is not valid UTF-8");
+    if c.is_ascii() {
+        return Ok(c as char);
+    }
+
+    let mut bytes = vec![c];
+    loop {
+        match next_buffered(buf, pos, input_available)? {
+            Some(next) => {
+                bytes.push(next);
+                if let Ok(st) = str::from_utf8(&bytes) {
+                    // unwrap is safe here because parse was OK
+                    return Ok(st.chars().next().unwrap());
+                }
+                if bytes.len() >= 4 {
+                    return Err(error());
+                }
+            }
+            None => return Err(error()),
+        }
+    }
+}
+
 /// Parse `c` as either a single byte ASCII char or a variable size UTF-8 char.
 fn parse_utf8_char<I>(c: u8, iter: &mut I) -> Result<char, Error>
 where
@@ -410,3 +1079,275 @@ fn test_parse_invalid_mouse() {
         )
     )
 }
+
+#[test]
+fn test_parse_modified_keys() {
+    // ESC [ 3 ; 2 ~ == Shift+Delete
+    let item = b'\x1B';
+    let mut iter = "[3;2~".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(event, Event::Key(Key::Delete, KeyModifiers::SHIFT));
+
+    // ESC [ 1 ; 5 C == Ctrl+Right
+    let item = b'\x1B';
+    let mut iter = "[1;5C".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(event, Event::Key(Key::Right, KeyModifiers::CTRL));
+
+    // A spurious third parameter is rejected here just like it is for the
+    // `~`-terminated arm above: ESC [ 1 ; 5 ; 9 C is not a valid Ctrl+Right.
+    let item = b'\x1B';
+    let mut iter = "[1;5;9C".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(
+        event,
+        Event::Unsupported(b"\x1B[1;5;9C".to_vec())
+    );
+}
+
+#[test]
+fn test_parse_bracketed_paste() {
+    let item = b'\x1B';
+    let mut iter = "[200~hello\nworld\x1B[201~".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(event, Event::Paste("hello\nworld".to_string()));
+}
+
+#[test]
+fn test_parse_unterminated_paste() {
+    let item = b'\x1B';
+    let mut iter = "[200~hello".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(
+        event,
+        Event::Unsupported(b"\x1B[200~hello".to_vec())
+    );
+}
+
+#[test]
+fn test_parse_focus_events() {
+    let item = b'\x1B';
+    let mut iter = "[I".bytes().map(|x| Ok(x));
+    assert_eq!(parse_event(item, &mut iter).unwrap().0, Event::FocusGained);
+
+    let item = b'\x1B';
+    let mut iter = "[O".bytes().map(|x| Ok(x));
+    assert_eq!(parse_event(item, &mut iter).unwrap().0, Event::FocusLost);
+}
+
+#[test]
+fn test_parse_event_buffered_incomplete() {
+    // A lone Esc is ambiguous while more input may still arrive.
+    assert_eq!(parse_event_buffered(b"\x1B", true).unwrap(), None);
+    // ...but unambiguous once we know no more input is coming.
+    assert_eq!(
+        parse_event_buffered(b"\x1B", false).unwrap(),
+        Some((Event::Key(Key::Esc, KeyModifiers::empty()), 1))
+    );
+
+    // A truncated CSI sequence waits for more bytes.
+    assert_eq!(parse_event_buffered(b"\x1B[1;", true).unwrap(), None);
+}
+
+#[test]
+fn test_parse_event_buffered_complete() {
+    assert_eq!(
+        parse_event_buffered(b"\x1B[1;5C", true).unwrap(),
+        Some((Event::Key(Key::Right, KeyModifiers::CTRL), 6))
+    );
+
+    // Only as many bytes as the event needs are consumed, leaving the rest
+    // of the buffer (e.g. a following keypress) untouched.
+    assert_eq!(
+        parse_event_buffered(b"\x1B[Da", true).unwrap(),
+        Some((Event::Key(Key::Left, KeyModifiers::empty()), 3))
+    );
+
+    // A spurious third parameter is rejected here too.
+    assert_eq!(
+        parse_event_buffered(b"\x1B[1;5;9C", true).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn test_parse_sgr_mouse_buffered() {
+    // Same "drag with left button held" report as
+    // `test_parse_sgr_mouse_drag_and_wheel`, but through the buffered path.
+    assert_eq!(
+        parse_event_buffered(b"\x1B[<32;11;21M", true).unwrap(),
+        Some((
+            Event::Mouse(MouseEvent::Drag(
+                MouseButton::Left,
+                MouseCoordinate::Cell(11, 21)
+            )),
+            12
+        ))
+    );
+
+    // A truncated SGR mouse report waits for more bytes.
+    assert_eq!(parse_event_buffered(b"\x1B[<32;11;21", true).unwrap(), None);
+}
+
+#[test]
+fn test_parse_bracketed_paste_buffered() {
+    assert_eq!(
+        parse_event_buffered(b"\x1B[200~hello\nworld\x1B[201~", true).unwrap(),
+        Some((Event::Paste("hello\nworld".to_string()), 23))
+    );
+
+    // A paste without its end marker yet waits for more bytes.
+    assert_eq!(
+        parse_event_buffered(b"\x1B[200~hello", true).unwrap(),
+        None
+    );
+    // ...but is reported as unsupported once no more input is coming.
+    assert_eq!(
+        parse_event_buffered(b"\x1B[200~hello", false).unwrap(),
+        Some((Event::Unsupported(b"\x1B[200~hello".to_vec()), 11))
+    );
+}
+
+#[test]
+fn test_parse_kitty_key_buffered() {
+    // ESC [ 105 ; 5 u == Ctrl+i, same as the iterator-based path.
+    assert_eq!(
+        parse_event_buffered(b"\x1B[105;5u", true).unwrap(),
+        Some((Event::Key(Key::Char('i'), KeyModifiers::CTRL), 8))
+    );
+
+    // ESC [ 57441 u == Kitty's Left-Shift codepoint, unmapped, must still be
+    // rejected rather than misread as a printable char. Unlike `parse_event`,
+    // the buffered path surfaces this as an error rather than downgrading it
+    // to `Event::Unsupported` itself -- callers are expected to do that.
+    assert_eq!(
+        parse_event_buffered(b"\x1B[57441u", true).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+
+    // A truncated Kitty key report waits for more bytes.
+    assert_eq!(parse_event_buffered(b"\x1B[105;5", true).unwrap(), None);
+}
+
+#[test]
+fn test_parse_sgr_mouse_drag_and_wheel() {
+    // Left button held while moving over cell (11, 21): cb = 0 | 32 (motion).
+    let item = b'\x1B';
+    let mut iter = "[<32;11;21M".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(
+        event,
+        Event::Mouse(MouseEvent::Drag(
+            MouseButton::Left,
+            MouseCoordinate::Cell(11, 21)
+        ))
+    );
+
+    // Wheel left/right.
+    let item = b'\x1B';
+    let mut iter = "[<66;5;5M".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(
+        event,
+        Event::Mouse(MouseEvent::Press(
+            MouseButton::WheelLeft,
+            MouseCoordinate::Cell(5, 5)
+        ))
+    );
+
+    // Plain motion, no button held: cb = 3 | 32.
+    let item = b'\x1B';
+    let mut iter = "[<35;7;8M".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(
+        event,
+        Event::Mouse(MouseEvent::Hold(MouseCoordinate::Cell(7, 8)))
+    );
+
+    // An "extra" button (bit 7, e.g. a side button) isn't modeled by
+    // `MouseButton`, but a drag with one held should still report a hold
+    // rather than vanishing as unsupported: cb = 128 | 32 (motion).
+    let item = b'\x1B';
+    let mut iter = "[<160;1;1M".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(
+        event,
+        Event::Mouse(MouseEvent::Hold(MouseCoordinate::Cell(1, 1)))
+    );
+
+    // An unclassified press (no motion bit) must still be distinguishable
+    // from its release: cb = 128, no motion.
+    let item = b'\x1B';
+    let mut iter = "[<128;1;1M".bytes().map(|x| Ok(x));
+    let (press, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(
+        press,
+        Event::Mouse(MouseEvent::Hold(MouseCoordinate::Cell(1, 1)))
+    );
+
+    let item = b'\x1B';
+    let mut iter = "[<128;1;1m".bytes().map(|x| Ok(x));
+    let (release, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(
+        release,
+        Event::Mouse(MouseEvent::Release(MouseCoordinate::Cell(1, 1)))
+    );
+    assert_ne!(press, release);
+}
+
+#[test]
+fn test_parse_sgr_mouse_pixels() {
+    let item = b'\x1B';
+    let mut iter = "[<0;100;200M".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event_ext(item, &mut iter, true).unwrap();
+    assert_eq!(
+        event,
+        Event::Mouse(MouseEvent::Press(
+            MouseButton::Left,
+            MouseCoordinate::Pixel(100, 200)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_kitty_key() {
+    // ESC [ 105 ; 5 u == Ctrl+i, distinct from Tab even though a legacy
+    // terminal would report both as `\t`.
+    let item = b'\x1B';
+    let mut iter = "[105;5u".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(event, Event::Key(Key::Char('i'), KeyModifiers::CTRL));
+
+    // ESC [ 57364 u == F1, with no modifiers.
+    let item = b'\x1B';
+    let mut iter = "[57364u".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(event, Event::Key(Key::F(1), KeyModifiers::empty()));
+
+    // ESC [ 97 ; 9 : 2 u == repeat of `a` held with Super.
+    let item = b'\x1B';
+    let mut iter = "[97;9:2u".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(
+        event,
+        Event::KeyRepeat(Key::Char('a'), KeyModifiers::SUPER)
+    );
+
+    // ESC [ 97 ; 1 : 3 u == release of `a` with no modifiers.
+    let item = b'\x1B';
+    let mut iter = "[97;1:3u".bytes().map(|x| Ok(x));
+    let (event, _) = parse_event(item, &mut iter).unwrap();
+    assert_eq!(
+        event,
+        Event::KeyRelease(Key::Char('a'), KeyModifiers::empty())
+    );
+
+    // ESC [ 57441 u == Kitty's Left-Shift codepoint, a functional key this
+    // mapping doesn't cover yet; it must not be misread as a printable char.
+    let item = b'\x1B';
+    let mut iter = "[57441u".bytes().map(|x| Ok(x));
+    assert_eq!(
+        parse_event(item, &mut iter).unwrap().0,
+        Event::Unsupported(b"\x1B[57441u".to_vec())
+    );
+}